@@ -1,79 +1,516 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use cli_table::{print_stdout, Cell, CellStruct, Style, Table};
 use rayon::prelude::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     env,
-    fs::File,
-    io::{BufRead, BufReader},
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
     path::Path,
-    time::Instant,
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
 };
 
-#[derive(Deserialize)]
-struct Log {
-    #[serde(rename = "type")]
-    log_type: String,
+/// How often `--follow` mode polls the source for newly appended lines.
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Where log lines are read from: a file path, `-` for stdin, or (combined
+/// with `--follow`) a file that is being appended to by another process.
+enum Source {
+    Stdin,
+    Path(String),
+}
+
+impl Source {
+    fn parse(arg: &str) -> Self {
+        if arg == "-" {
+            Self::Stdin
+        } else {
+            Self::Path(arg.to_string())
+        }
+    }
+
+    fn open(&self) -> Result<Box<dyn BufRead>> {
+        match self {
+            Self::Stdin => Ok(Box::new(BufReader::new(io::stdin()))),
+            Self::Path(path) => Ok(Box::new(BufReader::new(File::open(path)?))),
+        }
+    }
+
+    fn describe(&self) -> &str {
+        match self {
+            Self::Stdin => "stdin",
+            Self::Path(path) => path,
+        }
+    }
+}
+
+/// Reads whatever newline-terminated lines are currently available from
+/// `reader`, carrying any trailing unterminated bytes over in `pending` so a
+/// later poll (in `--follow` mode) can complete it once the writer catches
+/// up. Returns on EOF rather than blocking, so callers drive the polling.
+fn drain_lines(
+    reader: &mut dyn BufRead,
+    pending: &mut Vec<u8>,
+    start_index: usize,
+) -> io::Result<Vec<(usize, Vec<u8>)>> {
+    let mut lines = Vec::new();
+
+    loop {
+        let mut buf = Vec::new();
+        let read = reader.read_until(b'\n', &mut buf)?;
+        if read == 0 {
+            break;
+        }
+
+        if buf.last() == Some(&b'\n') {
+            buf.pop();
+            if !pending.is_empty() {
+                pending.append(&mut buf);
+                buf = std::mem::take(pending);
+            }
+            lines.push((start_index + lines.len(), buf));
+        } else {
+            pending.append(&mut buf);
+            break;
+        }
+    }
+
+    Ok(lines)
 }
 
 struct TypeData {
     total_byte_size: usize,
     num_instances: usize,
+    min_byte_size: usize,
+    max_byte_size: usize,
+    sizes: QuantileSketch,
+}
+
+impl TypeData {
+    fn single(byte_size: usize) -> Self {
+        Self {
+            total_byte_size: byte_size,
+            num_instances: 1,
+            min_byte_size: byte_size,
+            max_byte_size: byte_size,
+            sizes: QuantileSketch::single(byte_size as f64),
+        }
+    }
+
+    fn add(&mut self, byte_size: usize) {
+        self.num_instances += 1;
+        self.total_byte_size += byte_size;
+        self.min_byte_size = self.min_byte_size.min(byte_size);
+        self.max_byte_size = self.max_byte_size.max(byte_size);
+        self.sizes.add(byte_size as f64);
+    }
+
+    fn mean_byte_size(&self) -> f64 {
+        self.total_byte_size as f64 / self.num_instances as f64
+    }
+
+    fn merge(self, other: Self) -> Self {
+        Self {
+            num_instances: self.num_instances + other.num_instances,
+            total_byte_size: self.total_byte_size + other.total_byte_size,
+            min_byte_size: self.min_byte_size.min(other.min_byte_size),
+            max_byte_size: self.max_byte_size.max(other.max_byte_size),
+            sizes: self.sizes.merge(other.sizes),
+        }
+    }
+}
+
+/// Number of centroids a `QuantileSketch` is compressed down to, bounding its
+/// memory to O(1) per type regardless of how many lines are observed.
+const MAX_CENTROIDS: usize = 32;
+
+#[derive(Clone)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// A t-digest-style streaming quantile estimate: observations are folded
+/// into a small, weighted set of centroids, which are periodically merged
+/// and compressed back down to `MAX_CENTROIDS` so the sketch never grows
+/// unbounded. Two sketches combine by concatenating their centroids and
+/// re-compressing, which is what makes this mergeable across the
+/// parallel fold/reduce in `TypeTable::from_lines`.
+#[derive(Clone)]
+struct QuantileSketch {
+    centroids: Vec<Centroid>,
+}
+
+impl QuantileSketch {
+    fn single(value: f64) -> Self {
+        Self {
+            centroids: vec![Centroid {
+                mean: value,
+                weight: 1.0,
+            }],
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.centroids.push(Centroid {
+            mean: value,
+            weight: 1.0,
+        });
+
+        if self.centroids.len() > MAX_CENTROIDS * 4 {
+            self.compress();
+        }
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        self.centroids.extend(other.centroids);
+        self.compress();
+        self
+    }
+
+    fn compress(&mut self) {
+        if self.centroids.len() <= MAX_CENTROIDS {
+            return;
+        }
+
+        self.centroids
+            .sort_by(|a, b| a.mean.partial_cmp(&b.mean).unwrap());
+
+        let chunk_size = self.centroids.len().div_ceil(MAX_CENTROIDS);
+        self.centroids = self
+            .centroids
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let weight: f64 = chunk.iter().map(|c| c.weight).sum();
+                let mean = chunk.iter().map(|c| c.mean * c.weight).sum::<f64>() / weight;
+                Centroid { mean, weight }
+            })
+            .collect();
+    }
+
+    /// Estimates the value at quantile `q` (0.0..=1.0), e.g. `0.5` for the
+    /// median.
+    fn quantile(&self, q: f64) -> Option<f64> {
+        let mut sorted = self.centroids.clone();
+        sorted.sort_by(|a, b| a.mean.partial_cmp(&b.mean).unwrap());
+
+        let total_weight: f64 = sorted.iter().map(|c| c.weight).sum();
+        let target = q * total_weight;
+
+        let mut cumulative = 0.0;
+        for centroid in &sorted {
+            cumulative += centroid.weight;
+            if cumulative >= target {
+                return Some(centroid.mean);
+            }
+        }
+
+        sorted.last().map(|c| c.mean)
+    }
 }
 
 #[derive(Default)]
 struct TypeTable {
     types: HashMap<String, TypeData>,
     lines_excluded: Vec<ExcludedLine>,
+    config: Config,
+}
+
+impl TypeTable {
+    fn with_config(config: Config) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+}
+
+/// Controls which log types are tallied and which JSON field discriminates
+/// them, loaded from an optional `--config <file.toml>`.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+struct Config {
+    include: Vec<String>,
+    exclude: Vec<String>,
+    rename: HashMap<String, String>,
+    field: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            rename: HashMap::new(),
+            field: "type".to_string(),
+        }
+    }
+}
+
+impl Config {
+    fn from_file(path: &str) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        Ok(toml::from_str(&contents)?)
+    }
+
+    /// Canonicalizes a raw log type through the `rename` map, if aliased.
+    fn canonicalize(&self, log_type: &str) -> String {
+        self.rename
+            .get(log_type)
+            .cloned()
+            .unwrap_or_else(|| log_type.to_string())
+    }
+
+    /// Whether a (already-canonicalized) log type should be tallied.
+    fn is_included(&self, log_type: &str) -> bool {
+        if !self.include.is_empty() && !self.include.iter().any(|t| t == log_type) {
+            return false;
+        }
+
+        !self.exclude.iter().any(|t| t == log_type)
+    }
 }
 
 struct ExcludedLine {
     index: usize,
-    error: String,
+    diagnostic: Diagnostic,
 }
 
-impl TypeTable {
-    fn from_file(path: &str) -> Result<Self> {
-        let input_file = File::open(path)?;
+/// Mirrors the `level` field of rustc's `--error-format=json` diagnostics.
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Level {
+    Error,
+    Warn,
+    Note,
+}
 
-        let lines = BufReader::new(input_file).lines();
+/// A 1-based source range, in the style of rustc's JSON diagnostic spans.
+#[derive(Clone, Copy, Serialize)]
+struct Span {
+    line_start: usize,
+    column_start: usize,
+    line_end: usize,
+    column_end: usize,
+}
 
-        let mut type_table = Self::default();
+/// A machine-parseable diagnostic for a single line that failed to parse,
+/// modeled after rustc's `--error-format=json` output so editors and CI can
+/// map failures back to exact positions in the log file.
+#[derive(Serialize)]
+struct Diagnostic {
+    level: Level,
+    span: Span,
+    message: String,
+    rendered: Option<String>,
+}
+
+impl Diagnostic {
+    /// Builds a diagnostic for a line that failed to yield a log type,
+    /// classifying malformed UTF-8, malformed JSON, a missing discriminator
+    /// `field`, and a present-but-non-string `field` as distinct messages.
+    fn from_parse_failure(global_line: usize, raw: &[u8], field: &str) -> Self {
+        match std::str::from_utf8(raw) {
+            Err(utf8_err) => {
+                let column = utf8_err.valid_up_to() + 1;
+                Self {
+                    level: Level::Error,
+                    span: Span {
+                        line_start: global_line,
+                        column_start: column,
+                        line_end: global_line,
+                        column_end: column,
+                    },
+                    message: "malformed UTF-8 in log line".to_string(),
+                    rendered: Some(format!(
+                        "{}:{}: malformed UTF-8 in log line",
+                        global_line, column
+                    )),
+                }
+            }
+            Ok(text) => match serde_json::from_str::<serde_json::Value>(text) {
+                Err(err) => {
+                    let line = global_line + err.line().saturating_sub(1);
+                    let column = err.column();
+                    Self {
+                        level: Level::Error,
+                        span: Span {
+                            line_start: line,
+                            column_start: column,
+                            line_end: line,
+                            column_end: column,
+                        },
+                        message: "malformed JSON".to_string(),
+                        rendered: Some(format!(
+                            "{}:{}: {}",
+                            line,
+                            column,
+                            strip_position_suffix(&err.to_string())
+                        )),
+                    }
+                }
+                Ok(value) if value.get(field).is_none() => Self {
+                    level: Level::Warn,
+                    span: Span {
+                        line_start: global_line,
+                        column_start: 1,
+                        line_end: global_line,
+                        column_end: 1,
+                    },
+                    message: format!("missing required field `{}`", field),
+                    rendered: Some(format!(
+                        "{}:1: missing required field `{}`",
+                        global_line, field
+                    )),
+                },
+                Ok(_) => Self {
+                    level: Level::Note,
+                    span: Span {
+                        line_start: global_line,
+                        column_start: 1,
+                        line_end: global_line,
+                        column_end: 1,
+                    },
+                    message: format!("field `{}` is not a string", field),
+                    rendered: Some(format!(
+                        "{}:1: field `{}` is present but is not a string",
+                        global_line, field
+                    )),
+                },
+            },
+        }
+    }
+}
+
+/// Strips serde_json's trailing `" at line L column C"` from an error
+/// message so callers can re-attach the corrected global line number
+/// instead of the line-local one serde_json reports.
+fn strip_position_suffix(message: &str) -> &str {
+    message
+        .find(" at line ")
+        .map_or(message, |idx| &message[..idx])
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            other => bail!("unknown format \"{}\", expected table, json or csv", other),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TypeRecord<'a> {
+    #[serde(rename = "type")]
+    log_type: &'a str,
+    instances: usize,
+    total_byte_size: usize,
+    min_byte_size: usize,
+    max_byte_size: usize,
+    mean_byte_size: f64,
+    p50_byte_size: f64,
+}
+
+#[derive(Serialize)]
+struct ExcludedLineRecord<'a> {
+    /// 1-based, matching `diagnostic.span.line_start` and the table/CSV output.
+    index: usize,
+    #[serde(flatten)]
+    diagnostic: &'a Diagnostic,
+}
 
+impl TypeTable {
+    /// Aggregates a batch of `(global index, raw line)` pairs in parallel.
+    /// Used for both one-shot reads and each poll of `--follow` mode, so the
+    /// resulting partial table can be merged into a running total either way.
+    fn from_lines(lines: &[(usize, Vec<u8>)], config: &Config) -> Self {
         lines
-            .into_iter()
-            .enumerate()
-            .try_for_each(|(i, line)| -> Result<()> {
-                let line = line?;
-
-                match serde_json::from_str::<Log>(&line) {
-                    Ok(log) => match type_table.types.get_mut(&log.log_type) {
-                        Some(type_data) => {
-                            type_data.num_instances += 1;
-                            type_data.total_byte_size += line.len()
+            .par_iter()
+            .fold(
+                || Self::with_config(config.clone()),
+                |mut type_table, (i, raw)| {
+                    let field = &type_table.config.field;
+                    let raw_type = std::str::from_utf8(raw).ok().and_then(|text| {
+                        serde_json::from_str::<serde_json::Value>(text)
+                            .ok()
+                            .and_then(|value| value.get(field)?.as_str().map(str::to_string))
+                    });
+
+                    match raw_type {
+                        Some(raw_type) => {
+                            let log_type = type_table.config.canonicalize(&raw_type);
+                            if type_table.config.is_included(&log_type) {
+                                let byte_size = raw.len();
+                                match type_table.types.get_mut(&log_type) {
+                                    Some(type_data) => type_data.add(byte_size),
+                                    None => {
+                                        type_table
+                                            .types
+                                            .insert(log_type, TypeData::single(byte_size));
+                                    }
+                                }
+                            }
                         }
                         None => {
-                            type_table.types.insert(
-                                log.log_type,
-                                TypeData {
-                                    num_instances: 1,
-                                    total_byte_size: line.len(),
-                                },
-                            );
+                            let diagnostic =
+                                Diagnostic::from_parse_failure(*i + 1, raw, &type_table.config.field);
+                            type_table.lines_excluded.push(ExcludedLine {
+                                index: *i,
+                                diagnostic,
+                            });
                         }
-                    },
-                    Err(err) => type_table.lines_excluded.push(ExcludedLine {
-                        index: i,
-                        error: err.to_string(),
-                    }),
-                };
+                    };
+
+                    type_table
+                },
+            )
+            .reduce(|| Self::with_config(config.clone()), Self::merge)
+    }
+
+    fn from_source(source: &Source, config: Config) -> Result<Self> {
+        let mut reader = source.open()?;
+        let mut pending = Vec::new();
+        let mut lines = drain_lines(&mut *reader, &mut pending, 0)?;
+        if !pending.is_empty() {
+            let index = lines.len();
+            lines.push((index, std::mem::take(&mut pending)));
+        }
 
-                Ok(())
-            })?;
+        let mut type_table = Self::from_lines(&lines, &config);
+        type_table.config = config;
+        type_table.lines_excluded.sort_by_key(|e| e.index);
 
         Ok(type_table)
     }
+
+    fn merge(mut self, other: Self) -> Self {
+        for (type_name, other_data) in other.types {
+            let merged = match self.types.remove(&type_name) {
+                Some(type_data) => type_data.merge(other_data),
+                None => other_data,
+            };
+            self.types.insert(type_name, merged);
+        }
+
+        self.lines_excluded.extend(other.lines_excluded);
+
+        self
+    }
 }
 
 fn main() {
@@ -81,51 +518,168 @@ fn main() {
 
     let args: Vec<String> = env::args().collect();
 
-    let input_arg = args.get(1).cloned();
+    let format = match parse_format(&args) {
+        Ok(format) => format,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
+
+    let config = match parse_config(&args) {
+        Ok(config) => config,
+        Err(err) => {
+            println!("Error reading config: {}", err);
+            return;
+        }
+    };
+
+    let follow = args.iter().any(|arg| arg == "--follow");
+
+    let input_arg = positional_arg(&args);
     match input_arg {
-        Some(path) => match TypeTable::from_file(&path) {
-            Ok(type_table) => {
-                render_table(&type_table);
-
-                if !type_table.lines_excluded.is_empty() {
-                    println!("The following lines were excluded because of errors:");
-                    for excluded in type_table.lines_excluded {
-                        println!("- line {}: {}", excluded.index + 1, excluded.error)
-                    }
+        Some(arg) => {
+            let source = Source::parse(&arg);
+
+            if follow {
+                if let Err(err) = run_follow(&source, config, format) {
+                    println!("Error following {}: {}", source.describe(), err);
                 }
+                return;
+            }
 
-                let elapsed = Instant::now() - start;
-                print!(
-                    "Task succesfully completed in {} microseconds",
-                    elapsed.as_micros()
-                );
+            match TypeTable::from_source(&source, config) {
+                Ok(type_table) => {
+                    format_table(&type_table, format);
+
+                    let elapsed = Instant::now() - start;
+                    if matches!(format, Format::Table) {
+                        print!(
+                            "Task succesfully completed in {} microseconds",
+                            elapsed.as_micros()
+                        );
+                    }
+                }
+                Err(err) => println!("Error reading {}: {}", source.describe(), err),
             }
-            Err(err) => println!("Error reading file {}: {}", path, err),
-        },
+        }
         None => {
-            let exe_name = Path::new(&args[0]).iter().last().unwrap().to_str().unwrap();
+            let exe_name = Path::new(&args[0]).iter().next_back().unwrap().to_str().unwrap();
             println!(
-                "No input provided as an argument. Expected usage is: \"{} [filename]\"",
+                "No input provided as an argument. Expected usage is: \"{} [filename|-] [--format table|json|csv] [--config file.toml] [--follow]\"",
                 exe_name
             );
         }
     }
 }
 
+/// Keeps `source` open and re-aggregates newly appended lines into a running
+/// `TypeTable`, like `tail -f`. The source is polled every
+/// `FOLLOW_POLL_INTERVAL`; the table is reprinted after each poll that found
+/// new lines, and not after polls that found none.
+fn run_follow(source: &Source, config: Config, format: Format) -> Result<()> {
+    let mut reader = source.open()?;
+    let mut pending = Vec::new();
+    let mut type_table = TypeTable::with_config(config.clone());
+    let mut next_index = 0usize;
+
+    loop {
+        let lines = drain_lines(&mut *reader, &mut pending, next_index)?;
+
+        if !lines.is_empty() {
+            next_index += lines.len();
+            let batch = TypeTable::from_lines(&lines, &config);
+            type_table = type_table.merge(batch);
+            type_table.lines_excluded.sort_by_key(|e| e.index);
+            format_table(&type_table, format);
+        }
+
+        thread::sleep(FOLLOW_POLL_INTERVAL);
+    }
+}
+
+fn positional_arg(args: &[String]) -> Option<String> {
+    let mut skip_next = false;
+    for arg in args.iter().skip(1) {
+        if skip_next {
+            skip_next = false;
+            continue;
+        }
+
+        if arg == "--format" || arg == "--config" {
+            skip_next = true;
+        } else if !arg.starts_with("--") {
+            return Some(arg.clone());
+        }
+    }
+
+    None
+}
+
+fn parse_config(args: &[String]) -> Result<Config> {
+    for (i, arg) in args.iter().enumerate() {
+        if arg == "--config" {
+            let value = args
+                .get(i + 1)
+                .ok_or_else(|| anyhow::anyhow!("--config requires a value"))?;
+            return Config::from_file(value);
+        } else if let Some(value) = arg.strip_prefix("--config=") {
+            return Config::from_file(value);
+        }
+    }
+
+    Ok(Config::default())
+}
+
+fn parse_format(args: &[String]) -> Result<Format> {
+    for (i, arg) in args.iter().enumerate() {
+        if arg == "--format" {
+            let value = args
+                .get(i + 1)
+                .ok_or_else(|| anyhow::anyhow!("--format requires a value"))?;
+            return Format::from_str(value);
+        } else if let Some(value) = arg.strip_prefix("--format=") {
+            return Format::from_str(value);
+        }
+    }
+
+    Ok(Format::Table)
+}
+
+fn format_table(type_table: &TypeTable, format: Format) {
+    match format {
+        Format::Table => {
+            render_table(type_table);
+
+            if !type_table.lines_excluded.is_empty() {
+                println!("The following lines were excluded because of errors:");
+                for excluded in &type_table.lines_excluded {
+                    let diagnostic = &excluded.diagnostic;
+                    println!(
+                        "- line {}: {}",
+                        excluded.index + 1,
+                        diagnostic.rendered.as_deref().unwrap_or(&diagnostic.message)
+                    )
+                }
+            }
+        }
+        Format::Json => render_json(type_table),
+        Format::Csv => render_csv(type_table),
+    }
+}
+
 fn render_table(type_table: &TypeTable) {
     let mut table: Vec<Vec<CellStruct>> = Vec::with_capacity(type_table.types.len());
-    for (
-        type_name,
-        TypeData {
-            total_byte_size,
-            num_instances,
-        },
-    ) in &type_table.types
-    {
+    for (type_name, type_data) in &type_table.types {
+        let median = type_data.sizes.quantile(0.5).unwrap_or(0.0);
         table.push(vec![
             type_name.cell(),
-            num_instances.cell(),
-            total_byte_size.cell(),
+            type_data.num_instances.cell(),
+            type_data.total_byte_size.cell(),
+            type_data.min_byte_size.cell(),
+            type_data.max_byte_size.cell(),
+            format!("{:.1}", type_data.mean_byte_size()).cell(),
+            format!("{:.1}", median).cell(),
         ]);
     }
 
@@ -135,8 +689,207 @@ fn render_table(type_table: &TypeTable) {
             "type".cell().bold(true),
             "instances".cell().bold(true),
             "total byte size".cell().bold(true),
+            "min byte size".cell().bold(true),
+            "max byte size".cell().bold(true),
+            "mean byte size".cell().bold(true),
+            "p50 byte size".cell().bold(true),
         ])
         .bold(true);
 
     print_stdout(table).ok();
 }
+
+fn render_json(type_table: &TypeTable) {
+    for (type_name, type_data) in &type_table.types {
+        let record = TypeRecord {
+            log_type: type_name,
+            instances: type_data.num_instances,
+            total_byte_size: type_data.total_byte_size,
+            min_byte_size: type_data.min_byte_size,
+            max_byte_size: type_data.max_byte_size,
+            mean_byte_size: type_data.mean_byte_size(),
+            p50_byte_size: type_data.sizes.quantile(0.5).unwrap_or(0.0),
+        };
+        if let Ok(line) = serde_json::to_string(&record) {
+            println!("{}", line);
+        }
+    }
+
+    for excluded in &type_table.lines_excluded {
+        let record = ExcludedLineRecord {
+            index: excluded.index + 1,
+            diagnostic: &excluded.diagnostic,
+        };
+        if let Ok(line) = serde_json::to_string(&record) {
+            println!("{}", line);
+        }
+    }
+}
+
+/// Quotes a CSV field per RFC 4180 if it contains a comma, quote, or
+/// newline, doubling any internal quotes.
+fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') || value.contains('\r')
+    {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn render_csv(type_table: &TypeTable) {
+    // Excluded-line diagnostics go to stderr rather than a second table
+    // appended after a blank line, so stdout stays a single, valid CSV
+    // document that pipeline tooling can parse without special-casing.
+    println!("type,instances,total_byte_size,min_byte_size,max_byte_size,mean_byte_size,p50_byte_size");
+    for (type_name, type_data) in &type_table.types {
+        println!(
+            "{},{},{},{},{},{:.1},{:.1}",
+            csv_field(type_name),
+            type_data.num_instances,
+            type_data.total_byte_size,
+            type_data.min_byte_size,
+            type_data.max_byte_size,
+            type_data.mean_byte_size(),
+            type_data.sizes.quantile(0.5).unwrap_or(0.0),
+        );
+    }
+
+    if !type_table.lines_excluded.is_empty() {
+        eprintln!("line,error");
+        for excluded in &type_table.lines_excluded {
+            eprintln!(
+                "{},{}",
+                excluded.index + 1,
+                csv_field(&excluded.diagnostic.message)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_lines_chunking_matches_a_single_pass() {
+        let config = Config::default();
+        let raw_lines: Vec<&[u8]> = vec![
+            br#"{"type":"a"}"#,
+            br#"{"type":"b"}"#,
+            br#"{"type":"a"}"#,
+            br#"{"type":"a"}"#,
+            br#"{"type":"b"}"#,
+        ];
+        let lines: Vec<(usize, Vec<u8>)> = raw_lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| (i, line.to_vec()))
+            .collect();
+
+        let whole = TypeTable::from_lines(&lines, &config);
+
+        let (first_half, second_half) = lines.split_at(2);
+        let chunked = TypeTable::from_lines(first_half, &config)
+            .merge(TypeTable::from_lines(second_half, &config));
+
+        for type_name in ["a", "b"] {
+            let expected = &whole.types[type_name];
+            let actual = &chunked.types[type_name];
+            assert_eq!(actual.num_instances, expected.num_instances);
+            assert_eq!(actual.total_byte_size, expected.total_byte_size);
+        }
+        assert_eq!(whole.types["a"].num_instances, 3);
+        assert_eq!(whole.types["b"].num_instances, 2);
+    }
+
+    #[test]
+    fn type_data_merge_sums_counts_and_combines_extremes() {
+        let mut a = TypeData::single(10);
+        a.add(30);
+        let b = TypeData::single(5);
+
+        let merged = a.merge(b);
+
+        assert_eq!(merged.num_instances, 3);
+        assert_eq!(merged.total_byte_size, 45);
+        assert_eq!(merged.min_byte_size, 5);
+        assert_eq!(merged.max_byte_size, 30);
+    }
+
+    #[test]
+    fn quantile_sketch_merge_preserves_the_overall_extremes() {
+        let mut a = QuantileSketch::single(1.0);
+        a.add(2.0);
+        let mut b = QuantileSketch::single(3.0);
+        b.add(100.0);
+
+        let merged = a.merge(b);
+
+        assert_eq!(merged.quantile(0.0), Some(1.0));
+        assert_eq!(merged.quantile(1.0), Some(100.0));
+    }
+
+    #[test]
+    fn drain_lines_carries_a_partial_line_across_polls_and_keeps_global_index() {
+        let mut pending = Vec::new();
+
+        let mut first_poll = std::io::Cursor::new(b"first\nsecond\nthird-incomplete".to_vec());
+        let first_batch = drain_lines(&mut first_poll, &mut pending, 10).unwrap();
+        assert_eq!(
+            first_batch,
+            vec![(10, b"first".to_vec()), (11, b"second".to_vec())]
+        );
+        assert_eq!(pending, b"third-incomplete");
+
+        let mut second_poll = std::io::Cursor::new(b" done\nfourth\n".to_vec());
+        let second_batch = drain_lines(&mut second_poll, &mut pending, 12).unwrap();
+        assert_eq!(
+            second_batch,
+            vec![
+                (12, b"third-incomplete done".to_vec()),
+                (13, b"fourth".to_vec())
+            ]
+        );
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn diagnostic_classifies_malformed_utf8() {
+        let raw = vec![b'h', b'i', 0xFF, b'!'];
+        let diagnostic = Diagnostic::from_parse_failure(3, &raw, "type");
+
+        assert!(matches!(diagnostic.level, Level::Error));
+        assert_eq!(diagnostic.span.line_start, 3);
+        assert!(diagnostic.message.contains("UTF-8"));
+    }
+
+    #[test]
+    fn diagnostic_classifies_malformed_json() {
+        let raw = b"{not json";
+        let diagnostic = Diagnostic::from_parse_failure(4, raw, "type");
+
+        assert!(matches!(diagnostic.level, Level::Error));
+        assert_eq!(diagnostic.span.line_start, 4);
+        assert_eq!(diagnostic.message, "malformed JSON");
+        assert!(diagnostic.rendered.unwrap().starts_with("4:"));
+    }
+
+    #[test]
+    fn diagnostic_classifies_missing_field() {
+        let raw = br#"{"other":"x"}"#;
+        let diagnostic = Diagnostic::from_parse_failure(5, raw, "type");
+
+        assert!(matches!(diagnostic.level, Level::Warn));
+        assert_eq!(diagnostic.message, "missing required field `type`");
+    }
+
+    #[test]
+    fn diagnostic_classifies_non_string_field() {
+        let raw = br#"{"type":123}"#;
+        let diagnostic = Diagnostic::from_parse_failure(6, raw, "type");
+
+        assert!(matches!(diagnostic.level, Level::Note));
+        assert_eq!(diagnostic.message, "field `type` is not a string");
+    }
+}